@@ -0,0 +1,138 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::compiler::Compiler;
+use crate::diagnostics::{Diagnostic, DiagnosticKind};
+use crate::environment::Environment;
+use crate::interpreter::{self, RuntimeError};
+use crate::parser::{Expr, Stmt};
+use crate::resolver::Resolutions;
+use crate::token::Token;
+use crate::vm::Vm;
+
+/// An execution backend for a resolved program. Both the tree-walking
+/// interpreter and the bytecode VM implement this so `run` can stay agnostic
+/// about which one it drives.
+pub trait Interpreter {
+    fn interpret(
+        &mut self,
+        stmts: &[Stmt],
+        resolutions: &Resolutions,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<(), Vec<RuntimeError>>;
+
+    /// Diagnostic for the first construct in `stmts` this backend cannot
+    /// execute, or `None` when the whole program is supported. `run` consults
+    /// this before dispatching so an unsupported feature surfaces as a
+    /// pre-execution diagnostic (exit 65) rather than a runtime failure. The
+    /// default backend supports the full language.
+    fn unsupported(&self, _stmts: &[Stmt]) -> Option<Diagnostic> {
+        None
+    }
+}
+
+/// Adapter around the existing free-function tree-walk interpreter.
+pub struct TreeWalk;
+
+impl Interpreter for TreeWalk {
+    fn interpret(
+        &mut self,
+        stmts: &[Stmt],
+        resolutions: &Resolutions,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<(), Vec<RuntimeError>> {
+        interpreter::interpret(stmts, resolutions, env)
+    }
+}
+
+/// Compiles each program to bytecode and executes it on the stack machine.
+///
+/// The bytecode backend does not yet lower functions or calls, so programs
+/// that declare or invoke a function are rejected up front by [`unsupported`]
+/// rather than part-way through execution.
+///
+/// [`unsupported`]: Interpreter::unsupported
+#[derive(Default)]
+pub struct Bytecode {
+    vm: Vm,
+}
+
+impl Bytecode {
+    pub fn new() -> Bytecode {
+        Bytecode { vm: Vm::new() }
+    }
+}
+
+impl Interpreter for Bytecode {
+    fn interpret(
+        &mut self,
+        stmts: &[Stmt],
+        _resolutions: &Resolutions,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<(), Vec<RuntimeError>> {
+        // The VM resolves locals to stack slots at compile time, so it does
+        // not consult the resolver's side table.
+        let chunk = Compiler::new().compile(stmts)?;
+        self.vm.run(&chunk, env)
+    }
+
+    fn unsupported(&self, stmts: &[Stmt]) -> Option<Diagnostic> {
+        stmts.iter().find_map(unsupported_stmt)
+    }
+}
+
+/// The first function declaration or call reachable from `stmt`, described as a
+/// parse-stage diagnostic. Used by the bytecode backend's capability check.
+fn unsupported_stmt(stmt: &Stmt) -> Option<Diagnostic> {
+    match stmt {
+        Stmt::Function(name, _, _) => Some(unsupported_diagnostic(
+            name,
+            "the bytecode backend does not support functions yet",
+        )),
+        Stmt::Expression(expr) | Stmt::Print(expr) => unsupported_expr(expr),
+        Stmt::Var(_, initializer) => initializer.as_ref().and_then(unsupported_expr),
+        Stmt::Block(stmts) => stmts.iter().find_map(unsupported_stmt),
+        Stmt::If(condition, then_branch, else_branch) => unsupported_expr(condition)
+            .or_else(|| unsupported_stmt(then_branch))
+            .or_else(|| else_branch.as_deref().and_then(unsupported_stmt)),
+        Stmt::While(condition, body) => {
+            unsupported_expr(condition).or_else(|| unsupported_stmt(body))
+        }
+        Stmt::Return(_, value) => value.as_ref().and_then(unsupported_expr),
+    }
+}
+
+fn unsupported_expr(expr: &Expr) -> Option<Diagnostic> {
+    match expr {
+        Expr::Call(_, paren, _) => Some(unsupported_diagnostic(
+            paren,
+            "the bytecode backend does not support function calls yet",
+        )),
+        Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+            unsupported_expr(left).or_else(|| unsupported_expr(right))
+        }
+        Expr::Unary(_, right) => unsupported_expr(right),
+        Expr::Grouping(inner) => unsupported_expr(inner),
+        Expr::Assign(_, value) => unsupported_expr(value),
+        Expr::Variable(_) | Expr::Literal(_, _) => None,
+    }
+}
+
+fn unsupported_diagnostic(token: &Token, message: &str) -> Diagnostic {
+    Diagnostic {
+        kind: DiagnosticKind::Parse,
+        line: token.line,
+        column: token.column,
+        span: Some(token.span),
+        message: message.to_string(),
+    }
+}
+
+/// Select a backend from the `LOX_BACKEND` environment variable; defaults to
+/// the tree-walking interpreter when unset or unrecognized.
+pub fn from_env() -> Box<dyn Interpreter> {
+    match std::env::var("LOX_BACKEND").as_deref() {
+        Ok("vm") | Ok("bytecode") => Box::new(Bytecode::new()),
+        _ => Box::new(TreeWalk),
+    }
+}
@@ -0,0 +1,86 @@
+use crate::interpreter::Value;
+
+/// A single bytecode instruction. Operands are carried inline rather than as a
+/// separate byte stream so the VM can pattern-match directly on the opcode.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Op {
+    /// Push `constants[idx]` onto the stack.
+    Constant(usize),
+    Nil,
+    True,
+    False,
+    Pop,
+    /// Define a new global named `names[idx]` from the value on top of stack.
+    DefineGlobal(usize),
+    GetGlobal(usize),
+    SetGlobal(usize),
+    GetLocal(usize),
+    SetLocal(usize),
+    Equal,
+    Greater,
+    Less,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Not,
+    Negate,
+    Print,
+    /// Jump forward `offset` instructions if the top of stack is falsey.
+    JumpIfFalse(usize),
+    /// Unconditional forward jump of `offset` instructions.
+    Jump(usize),
+    /// Jump backwards `offset` instructions (used for loops).
+    Loop(usize),
+    /// Call the callable `argc` slots below the top of stack.
+    Call(usize),
+    Return,
+}
+
+/// A compiled unit: the opcodes, the constant pool they index into, the global
+/// name table, and a parallel line table so the VM can report runtime errors
+/// against the original source.
+#[derive(Clone, Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Op>,
+    pub constants: Vec<Value>,
+    pub names: Vec<String>,
+    pub lines: Vec<u32>,
+}
+
+impl Chunk {
+    pub fn new() -> Chunk {
+        Chunk {
+            code: Vec::new(),
+            constants: Vec::new(),
+            names: Vec::new(),
+            lines: Vec::new(),
+        }
+    }
+
+    /// Append `op` tagged with the source `line` it came from, returning the
+    /// index of the emitted instruction so callers can patch jumps later.
+    pub fn write(&mut self, op: Op, line: u32) -> usize {
+        self.code.push(op);
+        self.lines.push(line);
+        self.code.len() - 1
+    }
+
+    /// Append `value` to the constant pool and return its index. Equal values
+    /// are not deduplicated; each call adds a new entry.
+    pub fn add_constant(&mut self, value: Value) -> usize {
+        self.constants.push(value);
+        self.constants.len() - 1
+    }
+
+    /// Intern a global `name`, reusing an existing slot when possible.
+    pub fn add_name(&mut self, name: &str) -> usize {
+        match self.names.iter().position(|n| n == name) {
+            Some(idx) => idx,
+            None => {
+                self.names.push(name.to_string());
+                self.names.len() - 1
+            }
+        }
+    }
+}
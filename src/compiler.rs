@@ -0,0 +1,385 @@
+use crate::chunk::{Chunk, Op};
+use crate::interpreter::{RuntimeError, Value};
+use crate::parser::{Expr, Stmt};
+use crate::token::{Token, TokenType};
+
+/// Lowers the parsed AST into a [`Chunk`]. Locals are resolved to stack slots
+/// at compile time; anything not found in an enclosing block scope is treated
+/// as a global and looked up by name at runtime.
+#[derive(Default)]
+pub struct Compiler {
+    chunk: Chunk,
+    locals: Vec<Local>,
+    scope_depth: usize,
+}
+
+struct Local {
+    name: String,
+    depth: usize,
+}
+
+impl Compiler {
+    pub fn new() -> Compiler {
+        Compiler {
+            chunk: Chunk::new(),
+            locals: Vec::new(),
+            scope_depth: 0,
+        }
+    }
+
+    /// Compile a whole program, returning the finished chunk.
+    pub fn compile(mut self, stmts: &[Stmt]) -> Result<Chunk, Vec<RuntimeError>> {
+        let mut errors = Vec::new();
+        for stmt in stmts {
+            if let Err(err) = self.statement(stmt) {
+                errors.push(err);
+            }
+        }
+        self.chunk.write(Op::Return, 0);
+        if errors.is_empty() {
+            Ok(self.chunk)
+        } else {
+            Err(errors)
+        }
+    }
+
+    fn statement(&mut self, stmt: &Stmt) -> Result<(), RuntimeError> {
+        match stmt {
+            Stmt::Expression(expr) => {
+                self.expression(expr)?;
+                self.chunk.write(Op::Pop, line_of(expr));
+            }
+            Stmt::Print(expr) => {
+                self.expression(expr)?;
+                self.chunk.write(Op::Print, line_of(expr));
+            }
+            Stmt::Var(name, initializer) => {
+                match initializer {
+                    Some(expr) => self.expression(expr)?,
+                    None => {
+                        self.chunk.write(Op::Nil, name.line);
+                    }
+                }
+                self.declare(name);
+            }
+            Stmt::Block(stmts) => {
+                self.begin_scope();
+                for stmt in stmts {
+                    self.statement(stmt)?;
+                }
+                self.end_scope();
+            }
+            Stmt::If(condition, then_branch, else_branch) => {
+                self.expression(condition)?;
+                let then_jump = self.chunk.write(Op::JumpIfFalse(0), line_of(condition));
+                self.chunk.write(Op::Pop, line_of(condition));
+                self.statement(then_branch)?;
+                let else_jump = self.chunk.write(Op::Jump(0), line_of(condition));
+                self.patch_jump(then_jump);
+                self.chunk.write(Op::Pop, line_of(condition));
+                if let Some(else_branch) = else_branch {
+                    self.statement(else_branch)?;
+                }
+                self.patch_jump(else_jump);
+            }
+            Stmt::While(condition, body) => {
+                let loop_start = self.chunk.code.len();
+                self.expression(condition)?;
+                let exit_jump = self.chunk.write(Op::JumpIfFalse(0), line_of(condition));
+                self.chunk.write(Op::Pop, line_of(condition));
+                self.statement(body)?;
+                // `Op::Loop` rewinds `ip` and then `continue`s without the
+                // trailing `ip += 1` that forward jumps rely on, so the offset
+                // is the exact distance back to `loop_start` with no fudge.
+                let offset = self.chunk.code.len() - loop_start;
+                self.chunk.write(Op::Loop(offset), line_of(condition));
+                self.patch_jump(exit_jump);
+                self.chunk.write(Op::Pop, line_of(condition));
+            }
+            Stmt::Return(keyword, value) => {
+                match value {
+                    Some(expr) => self.expression(expr)?,
+                    None => {
+                        self.chunk.write(Op::Nil, keyword.line);
+                    }
+                }
+                self.chunk.write(Op::Return, keyword.line);
+            }
+            Stmt::Function(..) => unreachable!(
+                "functions are rejected by Interpreter::unsupported before the \
+                 bytecode backend compiles"
+            ),
+        }
+        Ok(())
+    }
+
+    fn expression(&mut self, expr: &Expr) -> Result<(), RuntimeError> {
+        match expr {
+            Expr::Literal(value, line) => {
+                let idx = self.chunk.add_constant(value.clone());
+                self.chunk.write(Op::Constant(idx), *line);
+            }
+            Expr::Grouping(inner) => self.expression(inner)?,
+            Expr::Unary(operator, right) => {
+                self.expression(right)?;
+                match operator.token_type {
+                    TokenType::Minus => {
+                        self.chunk.write(Op::Negate, operator.line);
+                    }
+                    TokenType::Bang => {
+                        self.chunk.write(Op::Not, operator.line);
+                    }
+                    _ => unreachable!("unary parser produced {:?}", operator.token_type),
+                }
+            }
+            Expr::Binary(left, operator, right) => {
+                self.expression(left)?;
+                self.expression(right)?;
+                self.binary_op(operator);
+            }
+            Expr::Logical(left, operator, right) => {
+                self.expression(left)?;
+                let jump = match operator.token_type {
+                    TokenType::And => self.chunk.write(Op::JumpIfFalse(0), operator.line),
+                    _ => {
+                        // `or`: short-circuit when the left is truthy.
+                        let else_jump = self.chunk.write(Op::JumpIfFalse(0), operator.line);
+                        let end_jump = self.chunk.write(Op::Jump(0), operator.line);
+                        self.patch_jump(else_jump);
+                        self.chunk.write(Op::Pop, operator.line);
+                        self.expression(right)?;
+                        self.patch_jump(end_jump);
+                        return Ok(());
+                    }
+                };
+                self.chunk.write(Op::Pop, operator.line);
+                self.expression(right)?;
+                self.patch_jump(jump);
+            }
+            Expr::Variable(name) => {
+                match self.resolve_local(name) {
+                    Some(slot) => self.chunk.write(Op::GetLocal(slot), name.line),
+                    None => {
+                        let idx = self.chunk.add_name(&name.lexeme);
+                        self.chunk.write(Op::GetGlobal(idx), name.line)
+                    }
+                };
+            }
+            Expr::Assign(name, value) => {
+                self.expression(value)?;
+                match self.resolve_local(name) {
+                    Some(slot) => self.chunk.write(Op::SetLocal(slot), name.line),
+                    None => {
+                        let idx = self.chunk.add_name(&name.lexeme);
+                        self.chunk.write(Op::SetGlobal(idx), name.line)
+                    }
+                };
+            }
+            Expr::Call(callee, paren, arguments) => {
+                self.expression(callee)?;
+                for argument in arguments {
+                    self.expression(argument)?;
+                }
+                self.chunk.write(Op::Call(arguments.len()), paren.line);
+            }
+        }
+        Ok(())
+    }
+
+    fn binary_op(&mut self, operator: &Token) {
+        let line = operator.line;
+        match operator.token_type {
+            TokenType::Plus => self.chunk.write(Op::Add, line),
+            TokenType::Minus => self.chunk.write(Op::Subtract, line),
+            TokenType::Star => self.chunk.write(Op::Multiply, line),
+            TokenType::Slash => self.chunk.write(Op::Divide, line),
+            TokenType::EqualEqual => self.chunk.write(Op::Equal, line),
+            TokenType::BangEqual => {
+                self.chunk.write(Op::Equal, line);
+                self.chunk.write(Op::Not, line)
+            }
+            TokenType::Greater => self.chunk.write(Op::Greater, line),
+            TokenType::GreaterEqual => {
+                self.chunk.write(Op::Less, line);
+                self.chunk.write(Op::Not, line)
+            }
+            TokenType::Less => self.chunk.write(Op::Less, line),
+            TokenType::LessEqual => {
+                self.chunk.write(Op::Greater, line);
+                self.chunk.write(Op::Not, line)
+            }
+            _ => unreachable!("binary parser produced {:?}", operator.token_type),
+        };
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if self.scope_depth == 0 {
+            let idx = self.chunk.add_name(&name.lexeme);
+            self.chunk.write(Op::DefineGlobal(idx), name.line);
+        } else {
+            self.locals.push(Local {
+                name: name.lexeme.clone(),
+                depth: self.scope_depth,
+            });
+        }
+    }
+
+    fn resolve_local(&self, name: &Token) -> Option<usize> {
+        self.locals
+            .iter()
+            .rposition(|local| local.name == name.lexeme)
+    }
+
+    fn begin_scope(&mut self) {
+        self.scope_depth += 1;
+    }
+
+    fn end_scope(&mut self) {
+        self.scope_depth -= 1;
+        while let Some(local) = self.locals.last() {
+            if local.depth <= self.scope_depth {
+                break;
+            }
+            self.locals.pop();
+            self.chunk.write(Op::Pop, 0);
+        }
+    }
+
+    /// Back-patch a forward jump emitted with a placeholder offset so it lands
+    /// on the instruction following the current end of the code.
+    fn patch_jump(&mut self, from: usize) {
+        let offset = self.chunk.code.len() - from - 1;
+        match &mut self.chunk.code[from] {
+            Op::JumpIfFalse(slot) | Op::Jump(slot) => *slot = offset,
+            other => unreachable!("tried to patch non-jump {:?}", other),
+        }
+    }
+}
+
+/// Best-effort source line for an expression, used when tagging opcodes.
+fn line_of(expr: &Expr) -> u32 {
+    match expr {
+        Expr::Literal(_, line) => *line,
+        Expr::Grouping(inner) => line_of(inner),
+        Expr::Unary(operator, _) => operator.line,
+        Expr::Binary(_, operator, _) => operator.line,
+        Expr::Logical(_, operator, _) => operator.line,
+        Expr::Variable(name) => name.line,
+        Expr::Assign(name, _) => name.line,
+        Expr::Call(_, paren, _) => paren.line,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Span;
+    use crate::environment::Environment;
+    use crate::vm::Vm;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    fn ident(name: &str) -> Token {
+        Token {
+            token_type: TokenType::Identifier,
+            lexeme: name.to_string(),
+            line: 1,
+            column: 1,
+            span: Span::new(0, name.len()),
+        }
+    }
+
+    fn op(token_type: TokenType, lexeme: &str) -> Token {
+        Token {
+            token_type,
+            lexeme: lexeme.to_string(),
+            line: 1,
+            column: 1,
+            span: Span::new(0, lexeme.len()),
+        }
+    }
+
+    fn num(n: f64) -> Expr {
+        Expr::Literal(Value::Number(n), 1)
+    }
+
+    fn var(name: &str) -> Expr {
+        Expr::Variable(ident(name))
+    }
+
+    fn assign(name: &str, value: Expr) -> Stmt {
+        Stmt::Expression(Expr::Assign(ident(name), Box::new(value)))
+    }
+
+    fn add(left: Expr, right: Expr) -> Expr {
+        Expr::Binary(Box::new(left), op(TokenType::Plus, "+"), Box::new(right))
+    }
+
+    /// Compile and run `stmts` against a fresh global environment, returning it
+    /// so assertions can inspect whatever the program assigned into it.
+    fn run(stmts: Vec<Stmt>) -> Rc<RefCell<Environment>> {
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let chunk = Compiler::new().compile(&stmts).expect("compiled");
+        Vm::new().run(&chunk, env.clone()).expect("ran");
+        env
+    }
+
+    #[test]
+    fn globals_round_trip_through_define_get_set() {
+        let stmts = vec![
+            Stmt::Var(ident("x"), Some(num(1.0))),
+            assign("x", add(var("x"), num(2.0))),
+        ];
+        let env = run(stmts);
+        assert_eq!(env.borrow().get("x"), Some(Value::Number(3.0)));
+    }
+
+    #[test]
+    fn locals_resolve_to_stack_slots_across_nested_blocks() {
+        let stmts = vec![
+            Stmt::Var(ident("result"), None),
+            Stmt::Block(vec![
+                Stmt::Var(ident("a"), Some(num(1.0))),
+                Stmt::Block(vec![
+                    Stmt::Var(ident("b"), Some(num(2.0))),
+                    assign("result", add(var("a"), var("b"))),
+                ]),
+            ]),
+        ];
+        let env = run(stmts);
+        assert_eq!(env.borrow().get("result"), Some(Value::Number(3.0)));
+    }
+
+    #[test]
+    fn if_else_takes_the_matching_branch() {
+        let stmts = vec![
+            Stmt::Var(ident("result"), None),
+            Stmt::If(
+                Expr::Literal(Value::Boolean(false), 1),
+                Box::new(assign("result", num(1.0))),
+                Some(Box::new(assign("result", num(2.0)))),
+            ),
+        ];
+        let env = run(stmts);
+        assert_eq!(env.borrow().get("result"), Some(Value::Number(2.0)));
+    }
+
+    #[test]
+    fn string_concatenation_uses_add() {
+        let stmts = vec![
+            Stmt::Var(ident("result"), None),
+            assign(
+                "result",
+                add(
+                    Expr::Literal(Value::String("foo".to_string()), 1),
+                    Expr::Literal(Value::String("bar".to_string()), 1),
+                ),
+            ),
+        ];
+        let env = run(stmts);
+        assert_eq!(
+            env.borrow().get("result"),
+            Some(Value::String("foobar".to_string()))
+        );
+    }
+}
@@ -0,0 +1,212 @@
+/// The stage a [`Diagnostic`] originated from. The kind alone determines the
+/// process exit code, so the mapping lives in one place (`exit_code`) instead
+/// of the ad-hoc matches that used to sit in `run`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiagnosticKind {
+    Scan,
+    Parse,
+    /// A static scoping error from the resolver (self-reference in an
+    /// initializer, duplicate local, `return` outside a function). Kept
+    /// distinct from `Parse` so callers can tell a scoping mistake apart from
+    /// a syntax error even though both exit 65.
+    Resolve,
+    Runtime,
+}
+
+impl DiagnosticKind {
+    /// Conventional `jlox` exit codes: 65 for anything caught before
+    /// execution, 70 for a failure during it.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            DiagnosticKind::Scan | DiagnosticKind::Parse | DiagnosticKind::Resolve => 65,
+            DiagnosticKind::Runtime => 70,
+        }
+    }
+}
+
+/// A half-open byte range `[start, end)` into the original source.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+
+    /// True when `self` starts where `other` does and ends no later, i.e. it is
+    /// a prefix of `other`. Used to collapse a cascade of errors reported
+    /// against the same malformed construct.
+    fn is_prefix_of(&self, other: &Span) -> bool {
+        self.start == other.start && self.end <= other.end
+    }
+}
+
+/// Locate `lexeme`'s first occurrence on `line` (1-indexed) within `source`.
+/// `Token` carries its own column/span, so parse, resolve, and tree-walk
+/// runtime diagnostics no longer need this -- it remains a fallback for the
+/// bytecode backend's runtime errors, which are raised from an interned
+/// global name with no token to point at. Ambiguous whenever the lexeme
+/// repeats on its line, but that's strictly better than the alternative of
+/// no position at all. Falls back to the start of the line when the lexeme
+/// is empty or isn't found there.
+pub fn locate(source: &str, line: u32, lexeme: &str) -> (usize, Option<Span>) {
+    let mut offset = 0;
+    for (idx, text) in source.split('\n').enumerate() {
+        if idx as u32 + 1 != line {
+            offset += text.len() + 1;
+            continue;
+        }
+        if lexeme.is_empty() {
+            return (1, None);
+        }
+        return match text.find(lexeme) {
+            Some(pos) => (
+                text[..pos].chars().count() + 1,
+                Some(Span::new(offset + pos, offset + pos + lexeme.len())),
+            ),
+            None => (1, None),
+        };
+    }
+    (1, None)
+}
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub kind: DiagnosticKind,
+    pub line: u32,
+    pub column: usize,
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+/// Accumulates diagnostics across the pipeline, remembers which kinds have
+/// occurred, and renders them with a source caret. `run` reports into one of
+/// these and asks it for the first failing stage's exit code at the end.
+pub struct ErrorHandler<'a> {
+    source: &'a str,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> ErrorHandler<'a> {
+    pub fn new(source: &'a str) -> ErrorHandler<'a> {
+        ErrorHandler {
+            source,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    /// Record a diagnostic unless its span is a prefix of one already held, in
+    /// which case it is a redundant follow-on and is dropped.
+    pub fn report(&mut self, diagnostic: Diagnostic) {
+        if let Some(span) = diagnostic.span {
+            let redundant = self
+                .diagnostics
+                .iter()
+                .filter_map(|d| d.span)
+                .any(|existing| span.is_prefix_of(&existing));
+            if redundant {
+                return;
+            }
+        }
+        self.diagnostics.push(diagnostic);
+    }
+
+    pub fn had_error(&self, kind: DiagnosticKind) -> bool {
+        self.diagnostics.iter().any(|d| d.kind == kind)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.diagnostics.is_empty()
+    }
+
+    /// Take ownership of the accumulated diagnostics, e.g. to hand them to a
+    /// library caller inside a `LoxError`.
+    pub fn into_diagnostics(self) -> Vec<Diagnostic> {
+        self.diagnostics
+    }
+
+    /// The kind of the first reported diagnostic, which is the stage that
+    /// first failed and whose exit code `run` should use.
+    pub fn first_failing_kind(&self) -> Option<DiagnosticKind> {
+        self.diagnostics.first().map(|d| d.kind)
+    }
+
+    pub fn exit_code(&self) -> Option<i32> {
+        self.first_failing_kind().map(DiagnosticKind::exit_code)
+    }
+
+    /// Print every retained diagnostic with its source line and a caret
+    /// underlining the offending span, in the style of `rustc`.
+    pub fn render(&self) {
+        render(self.source, &self.diagnostics);
+    }
+}
+
+/// Render a slice of diagnostics against `source`. Shared by the in-pipeline
+/// [`ErrorHandler`] and library callers that inspect a `LoxError` after the
+/// fact.
+pub fn render(source: &str, diagnostics: &[Diagnostic]) {
+    for diagnostic in diagnostics {
+        render_one(source, diagnostic);
+    }
+}
+
+fn render_one(source: &str, diagnostic: &Diagnostic) {
+    println!(
+        "error[{:?}]: {} [line {}:{}]",
+        diagnostic.kind, diagnostic.message, diagnostic.line, diagnostic.column
+    );
+    if let Some(line_text) = source.lines().nth((diagnostic.line as usize).saturating_sub(1)) {
+        let gutter = format!("{} | ", diagnostic.line);
+        println!("{}{}", gutter, line_text);
+        let width = diagnostic
+            .span
+            .map(|s| s.end.saturating_sub(s.start).max(1))
+            .unwrap_or(1);
+        let pad = " ".repeat(gutter.len() + diagnostic.column.saturating_sub(1));
+        println!("{}{}", pad, "^".repeat(width));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locate_finds_the_lexeme_column_and_byte_span_on_its_line() {
+        let source = "var x = 1;\nprint y + 2;\n";
+        let (column, span) = locate(source, 2, "y");
+        assert_eq!(column, 7);
+        assert_eq!(span, Some(Span::new(17, 18)));
+    }
+
+    #[test]
+    fn locate_falls_back_when_the_lexeme_is_missing_or_empty() {
+        let source = "var x = 1;\n";
+        assert_eq!(locate(source, 2, "y"), (1, None));
+        assert_eq!(locate(source, 1, ""), (1, None));
+    }
+
+    #[test]
+    fn report_collapses_a_diagnostic_whose_span_is_a_prefix_of_one_already_held() {
+        let mut errors = ErrorHandler::new("whatever + ;");
+        errors.report(Diagnostic {
+            kind: DiagnosticKind::Parse,
+            line: 1,
+            column: 1,
+            span: Some(Span::new(0, 12)),
+            message: "expected expression after '+'".to_string(),
+        });
+        errors.report(Diagnostic {
+            kind: DiagnosticKind::Parse,
+            line: 1,
+            column: 1,
+            span: Some(Span::new(0, 4)),
+            message: "a cascade from the same malformed construct".to_string(),
+        });
+        assert_eq!(errors.into_diagnostics().len(), 1);
+    }
+}
@@ -0,0 +1,101 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::interpreter::Value;
+
+/// `name` isn't declared anywhere in the environment chain being assigned
+/// into. Carries no data of its own: the caller already has the name and
+/// line, and turns this into a proper `RuntimeError` with those.
+#[derive(Debug)]
+pub struct UndefinedVariable;
+
+/// A lexical scope of variable bindings, linked to the scope it's nested in so
+/// a lookup can walk outward when a name isn't found locally. The outermost
+/// environment (the one passed in with no enclosing scope) holds globals.
+#[derive(Debug, Default)]
+pub struct Environment {
+    values: HashMap<String, Value>,
+    enclosing: Option<Rc<RefCell<Environment>>>,
+}
+
+impl Environment {
+    pub fn new(enclosing: Option<Rc<RefCell<Environment>>>) -> Environment {
+        Environment {
+            values: HashMap::new(),
+            enclosing,
+        }
+    }
+
+    pub fn define(&mut self, name: String, value: Value) {
+        self.values.insert(name, value);
+    }
+
+    /// Look up `name`, searching this scope and then every enclosing scope in
+    /// turn.
+    pub fn get(&self, name: &str) -> Option<Value> {
+        match self.values.get(name) {
+            Some(value) => Some(value.clone()),
+            None => self
+                .enclosing
+                .as_ref()
+                .and_then(|enclosing| enclosing.borrow().get(name)),
+        }
+    }
+
+    /// Assign to an existing binding of `name`, searching outward the same
+    /// way [`get`](Environment::get) does. Errs without creating a new
+    /// binding if `name` isn't already declared anywhere in the chain.
+    pub fn assign(&mut self, name: &str, value: Value) -> Result<(), UndefinedVariable> {
+        if self.values.contains_key(name) {
+            self.values.insert(name.to_string(), value);
+            Ok(())
+        } else if let Some(enclosing) = &self.enclosing {
+            enclosing.borrow_mut().assign(name, value)
+        } else {
+            Err(UndefinedVariable)
+        }
+    }
+
+    /// The environment exactly `depth` enclosing links out from `start`, as
+    /// computed by the resolver's static scope analysis.
+    fn ancestor(start: &Rc<RefCell<Environment>>, depth: usize) -> Rc<RefCell<Environment>> {
+        let mut env = start.clone();
+        for _ in 0..depth {
+            let next = env
+                .borrow()
+                .enclosing
+                .clone()
+                .expect("resolver depth exceeds the live environment chain");
+            env = next;
+        }
+        env
+    }
+
+    /// Read `name` directly from the scope `depth` hops out from `start`,
+    /// bypassing the name search `get` does. Used for variable accesses the
+    /// resolver has already resolved to a fixed depth, so a closure keeps
+    /// reading the binding that was live when it was created rather than
+    /// whatever happens to be in scope by the time it runs.
+    pub fn get_at(start: &Rc<RefCell<Environment>>, depth: usize, name: &str) -> Option<Value> {
+        Environment::ancestor(start, depth).borrow().values.get(name).cloned()
+    }
+
+    /// Assign `name` directly in the scope `depth` hops out from `start`. See
+    /// [`get_at`](Environment::get_at).
+    pub fn assign_at(
+        start: &Rc<RefCell<Environment>>,
+        depth: usize,
+        name: &str,
+        value: Value,
+    ) -> Result<(), UndefinedVariable> {
+        let ancestor = Environment::ancestor(start, depth);
+        let mut ancestor = ancestor.borrow_mut();
+        if ancestor.values.contains_key(name) {
+            ancestor.values.insert(name.to_string(), value);
+            Ok(())
+        } else {
+            Err(UndefinedVariable)
+        }
+    }
+}
@@ -0,0 +1,502 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use crate::diagnostics::Span;
+use crate::environment::Environment;
+use crate::parser::{Expr, Stmt};
+use crate::resolver::{ExprId, Resolutions};
+use crate::token::{Token, TokenType};
+
+/// A runtime Lox value. Functions are reference-counted so a call can cheaply
+/// clone the value it looked up off the stack/environment without cloning the
+/// closure's captured environment.
+#[derive(Clone)]
+pub enum Value {
+    Number(f64),
+    String(String),
+    Boolean(bool),
+    Nil,
+    Function(Rc<LoxFunction>),
+}
+
+/// A user-defined function: its declaration plus the environment it closed
+/// over at the point it was declared, so a reference to an outer variable
+/// inside its body resolves against the bindings live then, not whatever is
+/// in scope wherever the function is later called from.
+pub struct LoxFunction {
+    name: String,
+    params: Vec<Token>,
+    body: Vec<Stmt>,
+}
+
+impl LoxFunction {
+    fn arity(&self) -> usize {
+        self.params.len()
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::String(s) => write!(f, "{}", s),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Nil => write!(f, "nil"),
+            Value::Function(function) => write!(f, "<fn {}>", function.name),
+        }
+    }
+}
+
+impl fmt::Debug for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "Number({:?})", n),
+            Value::String(s) => write!(f, "String({:?})", s),
+            Value::Boolean(b) => write!(f, "Boolean({:?})", b),
+            Value::Nil => write!(f, "Nil"),
+            Value::Function(function) => write!(f, "Function({:?})", function.name),
+        }
+    }
+}
+
+impl PartialEq for Value {
+    fn eq(&self, other: &Value) -> bool {
+        match (self, other) {
+            (Value::Number(a), Value::Number(b)) => a == b,
+            (Value::String(a), Value::String(b)) => a == b,
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Nil, Value::Nil) => true,
+            (Value::Function(a), Value::Function(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+}
+
+/// A tree-walk runtime error. `expr` is the lexeme of the offending token
+/// when there is one (e.g. an undefined variable's name), or empty for
+/// errors with no single associated token (e.g. a bad operand type).
+///
+/// `column`/`span` are the token's own position, carried straight through
+/// rather than re-derived later by searching the source line for `expr` --
+/// that search is ambiguous whenever the lexeme repeats on its line (e.g.
+/// `x = x + x` erroring on the right-hand `x`). The bytecode backend raises
+/// this same error type from plain interned names with no token to point
+/// at, so these are `None` there; `report_runtime` falls back to the
+/// line-search for those.
+#[derive(Debug)]
+pub struct RuntimeError {
+    pub expr: String,
+    pub line: u32,
+    pub column: Option<usize>,
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+fn error_at(token: &Token, message: impl Into<String>) -> RuntimeError {
+    RuntimeError {
+        expr: token.lexeme.clone(),
+        line: token.line,
+        column: Some(token.column),
+        span: Some(token.span),
+        message: message.into(),
+    }
+}
+
+fn undefined(name: &Token) -> RuntimeError {
+    error_at(name, format!("undefined variable '{}'", name.lexeme))
+}
+
+/// Unwinds a function call back out to the `return` that produced `Signal`,
+/// carrying the returned value. Falling off the end of a body without
+/// `return` is `Next`, which the caller treats as `Value::Nil`.
+enum Signal {
+    Next,
+    Return(Value),
+}
+
+/// Execute `stmts` for their side effects, stopping at the first runtime
+/// error.
+pub fn interpret(
+    stmts: &[Stmt],
+    resolutions: &Resolutions,
+    env: Rc<RefCell<Environment>>,
+) -> Result<(), Vec<RuntimeError>> {
+    let globals = env.clone();
+    for stmt in stmts {
+        execute(stmt, &globals, &env, resolutions).map_err(|e| vec![e])?;
+    }
+    Ok(())
+}
+
+/// Like [`interpret`], but returns the value of the program's last statement
+/// when that statement is an expression statement, and `Value::Nil` otherwise.
+pub fn evaluate(
+    stmts: &[Stmt],
+    resolutions: &Resolutions,
+    env: Rc<RefCell<Environment>>,
+) -> Result<Value, Vec<RuntimeError>> {
+    let globals = env.clone();
+    let mut result = Value::Nil;
+    for stmt in stmts {
+        result = match stmt {
+            Stmt::Expression(expr) => eval(expr, &globals, &env, resolutions).map_err(|e| vec![e])?,
+            _ => {
+                execute(stmt, &globals, &env, resolutions).map_err(|e| vec![e])?;
+                Value::Nil
+            }
+        };
+    }
+    Ok(result)
+}
+
+fn execute(
+    stmt: &Stmt,
+    globals: &Rc<RefCell<Environment>>,
+    env: &Rc<RefCell<Environment>>,
+    resolutions: &Resolutions,
+) -> Result<Signal, RuntimeError> {
+    match stmt {
+        Stmt::Expression(expr) => {
+            eval(expr, globals, env, resolutions)?;
+            Ok(Signal::Next)
+        }
+        Stmt::Print(expr) => {
+            let value = eval(expr, globals, env, resolutions)?;
+            println!("{}", value);
+            Ok(Signal::Next)
+        }
+        Stmt::Var(name, initializer) => {
+            let value = match initializer {
+                Some(expr) => eval(expr, globals, env, resolutions)?,
+                None => Value::Nil,
+            };
+            env.borrow_mut().define(name.lexeme.clone(), value);
+            Ok(Signal::Next)
+        }
+        Stmt::Function(name, params, body) => {
+            let function = Value::Function(Rc::new(LoxFunction {
+                name: name.lexeme.clone(),
+                params: params.to_vec(),
+                body: body.to_vec(),
+            }));
+            env.borrow_mut().define(name.lexeme.clone(), function);
+            Ok(Signal::Next)
+        }
+        Stmt::Block(stmts) => {
+            let block_env = Rc::new(RefCell::new(Environment::new(Some(env.clone()))));
+            execute_block(stmts, globals, &block_env, resolutions)
+        }
+        Stmt::If(condition, then_branch, else_branch) => {
+            if truthy(&eval(condition, globals, env, resolutions)?) {
+                execute(then_branch, globals, env, resolutions)
+            } else if let Some(else_branch) = else_branch {
+                execute(else_branch, globals, env, resolutions)
+            } else {
+                Ok(Signal::Next)
+            }
+        }
+        Stmt::While(condition, body) => {
+            while truthy(&eval(condition, globals, env, resolutions)?) {
+                match execute(body, globals, env, resolutions)? {
+                    Signal::Next => {}
+                    signal @ Signal::Return(_) => return Ok(signal),
+                }
+            }
+            Ok(Signal::Next)
+        }
+        Stmt::Return(_, value) => {
+            let value = match value {
+                Some(expr) => eval(expr, globals, env, resolutions)?,
+                None => Value::Nil,
+            };
+            Ok(Signal::Return(value))
+        }
+    }
+}
+
+fn execute_block(
+    stmts: &[Stmt],
+    globals: &Rc<RefCell<Environment>>,
+    env: &Rc<RefCell<Environment>>,
+    resolutions: &Resolutions,
+) -> Result<Signal, RuntimeError> {
+    for stmt in stmts {
+        match execute(stmt, globals, env, resolutions)? {
+            Signal::Next => {}
+            signal @ Signal::Return(_) => return Ok(signal),
+        }
+    }
+    Ok(Signal::Next)
+}
+
+fn eval(
+    expr: &Expr,
+    globals: &Rc<RefCell<Environment>>,
+    env: &Rc<RefCell<Environment>>,
+    resolutions: &Resolutions,
+) -> Result<Value, RuntimeError> {
+    match expr {
+        Expr::Literal(value, _) => Ok(value.clone()),
+        Expr::Grouping(inner) => eval(inner, globals, env, resolutions),
+        Expr::Unary(operator, right) => {
+            let right = eval(right, globals, env, resolutions)?;
+            match operator.token_type {
+                TokenType::Minus => match right {
+                    Value::Number(n) => Ok(Value::Number(-n)),
+                    _ => Err(error_at(operator, "operand must be a number")),
+                },
+                TokenType::Bang => Ok(Value::Boolean(!truthy(&right))),
+                _ => unreachable!("unary parser produced {:?}", operator.token_type),
+            }
+        }
+        Expr::Binary(left, operator, right) => {
+            let left = eval(left, globals, env, resolutions)?;
+            let right = eval(right, globals, env, resolutions)?;
+            binary_op(operator, left, right)
+        }
+        Expr::Logical(left, operator, right) => {
+            let left_value = eval(left, globals, env, resolutions)?;
+            match operator.token_type {
+                TokenType::Or if truthy(&left_value) => Ok(left_value),
+                TokenType::And if !truthy(&left_value) => Ok(left_value),
+                _ => eval(right, globals, env, resolutions),
+            }
+        }
+        // Resolved accesses read/write the exact scope the resolver found
+        // the declaration in; unresolved ones are globals, looked up
+        // straight in the outermost environment rather than by walking the
+        // current scope chain outward, so a local declared *after* a
+        // closure is created in the same block can't shadow the closure's
+        // (correctly unresolved-to-global) reference.
+        Expr::Variable(name) => {
+            let found = match resolutions.get(&ExprId::of(expr)) {
+                Some(&depth) => Environment::get_at(env, depth, &name.lexeme),
+                None => globals.borrow().get(&name.lexeme),
+            };
+            found.ok_or_else(|| undefined(name))
+        }
+        Expr::Assign(name, value) => {
+            let value = eval(value, globals, env, resolutions)?;
+            match resolutions.get(&ExprId::of(expr)) {
+                Some(&depth) => {
+                    Environment::assign_at(env, depth, &name.lexeme, value.clone())
+                        .map_err(|_| undefined(name))?;
+                }
+                None => {
+                    globals
+                        .borrow_mut()
+                        .assign(&name.lexeme, value.clone())
+                        .map_err(|_| undefined(name))?;
+                }
+            }
+            Ok(value)
+        }
+        Expr::Call(callee, paren, arguments) => {
+            let callee_value = eval(callee, globals, env, resolutions)?;
+            let mut args = Vec::with_capacity(arguments.len());
+            for argument in arguments {
+                args.push(eval(argument, globals, env, resolutions)?);
+            }
+            call(callee_value, args, paren, globals, resolutions)
+        }
+    }
+}
+
+fn call(
+    callee: Value,
+    arguments: Vec<Value>,
+    paren: &Token,
+    globals: &Rc<RefCell<Environment>>,
+    resolutions: &Resolutions,
+) -> Result<Value, RuntimeError> {
+    let function = match callee {
+        Value::Function(function) => function,
+        _ => return Err(error_at(paren, "can only call functions")),
+    };
+    if arguments.len() != function.arity() {
+        return Err(error_at(
+            paren,
+            format!(
+                "expected {} arguments but got {}",
+                function.arity(),
+                arguments.len()
+            ),
+        ));
+    }
+    // A fresh call frame, enclosed directly by globals: this interpreter
+    // resolves a function's own free variables as globals (see the
+    // `Expr::Variable`/`Expr::Assign` comment in `eval`), so the call frame
+    // only needs its parameters, not the defining scope's locals.
+    let call_env = Rc::new(RefCell::new(Environment::new(Some(globals.clone()))));
+    for (param, argument) in function.params.iter().zip(arguments) {
+        call_env.borrow_mut().define(param.lexeme.clone(), argument);
+    }
+    match execute_block(&function.body, globals, &call_env, resolutions)? {
+        Signal::Next => Ok(Value::Nil),
+        Signal::Return(value) => Ok(value),
+    }
+}
+
+fn binary_op(operator: &Token, left: Value, right: Value) -> Result<Value, RuntimeError> {
+    match operator.token_type {
+        TokenType::Plus => match (left, right) {
+            (Value::Number(a), Value::Number(b)) => Ok(Value::Number(a + b)),
+            (Value::String(a), Value::String(b)) => Ok(Value::String(format!("{}{}", a, b))),
+            _ => Err(error_at(operator, "operands must be two numbers or two strings")),
+        },
+        TokenType::Minus => arithmetic(operator, left, right, |a, b| a - b),
+        TokenType::Star => arithmetic(operator, left, right, |a, b| a * b),
+        TokenType::Slash => arithmetic(operator, left, right, |a, b| a / b),
+        TokenType::Greater => compare(operator, left, right, |a, b| a > b),
+        TokenType::GreaterEqual => compare(operator, left, right, |a, b| a >= b),
+        TokenType::Less => compare(operator, left, right, |a, b| a < b),
+        TokenType::LessEqual => compare(operator, left, right, |a, b| a <= b),
+        TokenType::EqualEqual => Ok(Value::Boolean(left == right)),
+        TokenType::BangEqual => Ok(Value::Boolean(left != right)),
+        _ => unreachable!("binary parser produced {:?}", operator.token_type),
+    }
+}
+
+fn arithmetic(
+    operator: &Token,
+    left: Value,
+    right: Value,
+    op: impl Fn(f64, f64) -> f64,
+) -> Result<Value, RuntimeError> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => Ok(Value::Number(op(a, b))),
+        _ => Err(error_at(operator, "operands must be numbers")),
+    }
+}
+
+fn compare(
+    operator: &Token,
+    left: Value,
+    right: Value,
+    op: impl Fn(f64, f64) -> bool,
+) -> Result<Value, RuntimeError> {
+    match (left, right) {
+        (Value::Number(a), Value::Number(b)) => Ok(Value::Boolean(op(a, b))),
+        _ => Err(error_at(operator, "operands must be numbers")),
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Nil | Value::Boolean(false))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ident(name: &str, line: u32) -> Token {
+        Token {
+            token_type: TokenType::Identifier,
+            lexeme: name.to_string(),
+            line,
+            column: 1,
+            span: Span::new(0, name.len()),
+        }
+    }
+
+    fn op(token_type: TokenType, lexeme: &str, line: u32) -> Token {
+        Token {
+            token_type,
+            lexeme: lexeme.to_string(),
+            line,
+            column: 1,
+            span: Span::new(0, lexeme.len()),
+        }
+    }
+
+    fn string(s: &str, line: u32) -> Expr {
+        Expr::Literal(Value::String(s.to_string()), line)
+    }
+
+    fn run(stmts: &[Stmt]) -> Rc<RefCell<Environment>> {
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        let resolutions = crate::resolver::resolve(stmts).expect("resolves cleanly");
+        interpret(stmts, &resolutions, env.clone()).expect("interprets cleanly");
+        env
+    }
+
+    #[test]
+    fn a_variable_redeclared_in_a_block_after_a_closure_is_created_does_not_shadow_it() {
+        // var a = "global";
+        // var first; var second;
+        // {
+        //   fun showA() { return a; }
+        //   first = showA();
+        //   var a = "block";
+        //   second = showA();
+        // }
+        // `showA`'s `a` is unresolved (global) at resolve time, because the
+        // block's own `var a` hasn't been declared yet when `fun showA` is
+        // resolved -- both calls must see the global "a", regardless of what
+        // gets bound into the block's own scope in between.
+        let show_a_body = vec![Stmt::Return(
+            ident("return", 1),
+            Some(Expr::Variable(ident("a", 1))),
+        )];
+        let stmts = vec![
+            Stmt::Var(ident("a", 1), Some(string("global", 1))),
+            Stmt::Var(ident("first", 1), None),
+            Stmt::Var(ident("second", 1), None),
+            Stmt::Block(vec![
+                Stmt::Function(ident("showA", 1), vec![], show_a_body),
+                Stmt::Expression(Expr::Assign(
+                    ident("first", 1),
+                    Box::new(Expr::Call(
+                        Box::new(Expr::Variable(ident("showA", 1))),
+                        op(TokenType::RightParen, ")", 1),
+                        vec![],
+                    )),
+                )),
+                Stmt::Var(ident("a", 1), Some(string("block", 1))),
+                Stmt::Expression(Expr::Assign(
+                    ident("second", 1),
+                    Box::new(Expr::Call(
+                        Box::new(Expr::Variable(ident("showA", 1))),
+                        op(TokenType::RightParen, ")", 1),
+                        vec![],
+                    )),
+                )),
+            ]),
+        ];
+
+        let env = run(&stmts);
+        assert_eq!(env.borrow().get("first"), Some(Value::String("global".to_string())));
+        assert_eq!(env.borrow().get("second"), Some(Value::String("global".to_string())));
+    }
+
+    #[test]
+    fn a_shadowed_inner_local_does_not_leak_into_the_outer_scope_it_shadows() {
+        // var result;
+        // {
+        //   var a = "outer";
+        //   {
+        //     var a = "inner";
+        //     a = "changed";
+        //   }
+        //   result = a;
+        // }
+        let stmts = vec![
+            Stmt::Var(ident("result", 1), None),
+            Stmt::Block(vec![
+                Stmt::Var(ident("a", 1), Some(string("outer", 1))),
+                Stmt::Block(vec![
+                    Stmt::Var(ident("a", 2), Some(string("inner", 2))),
+                    Stmt::Expression(Expr::Assign(ident("a", 3), Box::new(string("changed", 3)))),
+                ]),
+                Stmt::Expression(Expr::Assign(
+                    ident("result", 4),
+                    Box::new(Expr::Variable(ident("a", 4))),
+                )),
+            ]),
+        ];
+
+        let env = run(&stmts);
+        assert_eq!(env.borrow().get("result"), Some(Value::String("outer".to_string())));
+    }
+}
@@ -0,0 +1,191 @@
+//! The Lox language as an embeddable library. [`run`] executes a program for
+//! its side effects; [`eval`] additionally returns the value of the final
+//! expression. Both reuse a caller-supplied [`Environment`] so bindings can
+//! persist across calls (as the REPL relies on).
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+pub mod backend;
+pub mod chunk;
+pub mod compiler;
+pub mod diagnostics;
+pub mod environment;
+pub mod interpreter;
+pub mod parser;
+pub mod resolver;
+pub mod scanner;
+pub mod token;
+pub mod vm;
+
+use crate::diagnostics::{Diagnostic, DiagnosticKind, ErrorHandler, Span};
+use crate::environment::Environment;
+use crate::interpreter::Value;
+
+/// Failure from [`run`]/[`eval`]. Carries the accumulated diagnostics so a
+/// library caller can inspect them, plus the process exit code the CLI uses.
+#[derive(Debug)]
+pub struct LoxError {
+    pub exit_code: i32,
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl LoxError {
+    /// Render the carried diagnostics against the originating `source`.
+    pub fn report(&self, source: &str) {
+        diagnostics::render(source, &self.diagnostics);
+    }
+}
+
+/// Scan, parse, resolve, and execute `source` for its side effects, selecting
+/// the backend from the environment. Diagnostics are returned rather than
+/// printed so the caller decides how to surface them.
+pub fn run(source: &str, env: Rc<RefCell<Environment>>) -> Result<(), LoxError> {
+    let mut errors = ErrorHandler::new(source);
+    let stmts = match front_end(source, &mut errors) {
+        Some(resolved) => resolved,
+        None => return Err(finish(errors)),
+    };
+
+    let mut interpreter = backend::from_env();
+    if let Some(diagnostic) = interpreter.unsupported(&stmts.0) {
+        errors.report(diagnostic);
+        return Err(finish(errors));
+    }
+    if let Err(errs) = interpreter.interpret(&stmts.0, &stmts.1, env) {
+        report_runtime(&mut errors, source, errs);
+        return Err(finish(errors));
+    }
+    Ok(())
+}
+
+/// Like [`run`], but returns the value of the program's final expression
+/// statement (or `Value::Nil` when it doesn't end in one).
+pub fn eval(source: &str, env: Rc<RefCell<Environment>>) -> Result<Value, LoxError> {
+    let mut errors = ErrorHandler::new(source);
+    let (stmts, resolutions) = match front_end(source, &mut errors) {
+        Some(resolved) => resolved,
+        None => return Err(finish(errors)),
+    };
+
+    match interpreter::evaluate(&stmts, &resolutions, env) {
+        Ok(value) => Ok(value),
+        Err(errs) => {
+            report_runtime(&mut errors, source, errs);
+            Err(finish(errors))
+        }
+    }
+}
+
+/// Run the scan/parse/resolve stages shared by [`run`] and [`eval`], reporting
+/// any failure into `errors` and returning `None` when a stage fails.
+fn front_end(
+    source: &str,
+    errors: &mut ErrorHandler,
+) -> Option<(Vec<parser::Stmt>, resolver::Resolutions)> {
+    let tokens = match scanner::scan_tokens(source) {
+        Ok(tokens) => tokens,
+        Err(scanner::ScanError {
+            cause,
+            line,
+            position,
+        }) => {
+            let message = match cause {
+                scanner::ScanErrorType::BadChar(c) => format!("unexpected character {}", c),
+                scanner::ScanErrorType::UnterminatedString(s) => {
+                    format!("unterminated string {}", s)
+                }
+                scanner::ScanErrorType::NumberParseError(s, e) => {
+                    format!("could not parse {} as a number ({})", s, e)
+                }
+            };
+            errors.report(Diagnostic {
+                kind: DiagnosticKind::Scan,
+                line,
+                column: position,
+                span: Some(Span::new(position, position + 1)),
+                message,
+            });
+            return None;
+        }
+    };
+
+    let stmts = match parser::parse(&tokens[..]) {
+        Ok(stmts) => stmts,
+        Err(errs) => {
+            for parser::ParseError { token, message } in errs {
+                // A real token carries its own position; a synthetic one
+                // (e.g. a parse error with nothing to point at) falls back
+                // to the start of the file.
+                let (line, column, span) = match &token {
+                    Some(t) => (t.line, t.column, Some(t.span)),
+                    None => (0, 1, None),
+                };
+                errors.report(Diagnostic {
+                    kind: DiagnosticKind::Parse,
+                    line,
+                    column,
+                    span,
+                    message,
+                });
+            }
+            return None;
+        }
+    };
+
+    let resolutions = match resolver::resolve(&stmts) {
+        Ok(resolutions) => resolutions,
+        Err(errs) => {
+            for resolver::ResolveError { token, message } in errs {
+                errors.report(Diagnostic {
+                    kind: DiagnosticKind::Resolve,
+                    line: token.line,
+                    column: token.column,
+                    span: Some(token.span),
+                    message,
+                });
+            }
+            return None;
+        }
+    };
+
+    Some((stmts, resolutions))
+}
+
+fn report_runtime(errors: &mut ErrorHandler, source: &str, errs: Vec<interpreter::RuntimeError>) {
+    for interpreter::RuntimeError {
+        expr,
+        line,
+        column,
+        span,
+        message,
+    } in errs
+    {
+        // The tree-walk backend raises these from a real `Token` and already
+        // knows its exact column/span; the bytecode backend only has an
+        // interned name with no token to point at, so it leaves both `None`
+        // and we fall back to searching the line for `expr` (ambiguous if
+        // the lexeme repeats, but better than nothing).
+        let (column, span) = match (column, span) {
+            (Some(column), Some(span)) => (column, Some(span)),
+            _ => diagnostics::locate(source, line, &expr),
+        };
+        errors.report(Diagnostic {
+            kind: DiagnosticKind::Runtime,
+            line,
+            column,
+            span,
+            message: format!("{} ({})", message, expr),
+        });
+    }
+}
+
+/// Translate the accumulated diagnostics into a `LoxError`, deriving the exit
+/// code from the first failing stage.
+fn finish(errors: ErrorHandler) -> LoxError {
+    let exit_code = errors.exit_code().unwrap_or(70);
+    LoxError {
+        exit_code,
+        diagnostics: errors.into_diagnostics(),
+    }
+}
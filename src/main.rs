@@ -1,22 +1,34 @@
 use std::cell::RefCell;
-use std::env;
-use std::fs::File;
-use std::io::prelude::*;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-pub mod environment;
-mod interpreter;
-pub mod parser;
-mod scanner;
-pub mod token;
+
+use clap::{Parser, Subcommand};
 use log::LevelFilter;
+use rustyline::error::ReadlineError;
+use rustyline::Editor;
 use simple_logger::SimpleLogger;
 
-use crate::environment::Environment;
-use crate::interpreter::interpret;
+use lox::environment::Environment;
+use lox::scanner;
+use lox::token::TokenType;
+
+#[derive(Parser)]
+#[command(name = "lox", about = "A tree-walking and bytecode interpreter for Lox")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
 
-struct LoxError {
-    exit_code: i32,
+#[derive(Subcommand)]
+enum Command {
+    /// Execute a script file.
+    Run { file: String },
+    /// Evaluate a one-off snippet and print its value.
+    Eval {
+        #[arg(short = 'c', long = "code")]
+        code: String,
+    },
 }
 
 fn main() {
@@ -24,123 +36,185 @@ fn main() {
         .with_level(LevelFilter::Warn)
         .init()
         .unwrap();
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 2 {
-        println!("Usage: jlox [script]");
-        std::process::exit(64);
-    } else if args.len() == 2 {
-        run_file(&args[1]);
-    } else {
-        run_prompt();
+    let cli = Cli::parse();
+    match cli.command {
+        Some(Command::Run { file }) => run_file(&file),
+        Some(Command::Eval { code }) => eval_snippet(&code),
+        None => run_prompt(),
     }
 }
 
 fn run_file(path_str: &str) -> () {
     let path = Path::new(path_str);
-    let display = path.display();
-
-    let mut file = match File::open(&path) {
-        Err(why) => panic!("couldn't open {}: {}", path_str, why),
-        Ok(file) => file,
+    let bytes = match fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(why) => {
+            eprintln!("couldn't read {}: {}", path_str, why);
+            std::process::exit(66);
+        }
     };
+    // Byte-based, lossy decode: invalid UTF-8 is replaced rather than
+    // panicking, and any resulting bad character surfaces as an ordinary
+    // scanner diagnostic.
+    let source = String::from_utf8_lossy(&bytes).into_owned();
 
-    let mut s = String::new();
     let env = Rc::new(RefCell::new(Environment::new(None)));
-    match file.read_to_string(&mut s) {
-        Err(why) => panic!("couldn't read {}: {}", display, why),
-        Ok(_) => match run(&s, env) {
-            Ok(_) => (),
-            Err(err) => std::process::exit(err.exit_code),
-        },
+    if let Err(err) = lox::run(&source, env) {
+        err.report(&source);
+        std::process::exit(err.exit_code);
     }
 }
 
-fn run_prompt() -> () {
-    let mut line = 1;
+fn eval_snippet(code: &str) -> () {
     let env = Rc::new(RefCell::new(Environment::new(None)));
-    loop {
-        print!("[{}] ", line);
-        match std::io::stdout().flush() {
-            Ok(_) => {}
-            Err(_) => panic!("flushing stdout resulted in an error, aborting"),
-        }
-        let mut input = String::new();
-        match std::io::stdin().read_line(&mut input) {
-            Ok(0) => {
-                break;
-            }
-            Ok(_) => match run(&input, env.clone()) {
-                Ok(_) => {
-                    line += 1;
-                }
-                Err(_) => line += 1,
-            },
-            Err(error) => println!("error: {}", error),
+    match lox::eval(code, env) {
+        Ok(value) => println!("{}", value),
+        Err(err) => {
+            err.report(code);
+            std::process::exit(err.exit_code);
         }
     }
 }
 
-fn run(source: &str, env: Rc<RefCell<Environment>>) -> Result<(), LoxError> {
-    match scanner::scan_tokens(source) {
-        Ok(tokens) => {
-            // for token in &tokens[..] {
-            //     println!("{:?}", token);
-            // }
-            match parser::parse(&tokens[..]) {
-                Ok(stmts) => match interpret(&stmts, env) {
-                    Ok(()) => return Ok(()),
-                    Err(errs) => {
-                        for interpreter::RuntimeError {
-                            expr,
-                            line,
-                            message,
-                        } in errs
-                        {
-                            println!("{} [line {}]: {}", message, line, expr);
-                        }
-                        return Err(LoxError { exit_code: 70 });
-                    }
-                },
-                Err(errs) => {
-                    for parser::ParseError { token, message } in errs {
-                        match token {
-                            Some(token) => error(
-                                token.line,
-                                format!("parser error on {:?}: {}", token, message),
-                            ),
-                            None => error(0, format!("parser error on {:?}: {}", token, message)),
-                        }
+fn run_prompt() -> () {
+    let env = Rc::new(RefCell::new(Environment::new(None)));
+    let mut rl = match Editor::<()>::new() {
+        Ok(rl) => rl,
+        Err(why) => panic!("couldn't start the line editor: {}", why),
+    };
+    let history = history_path();
+    if let Some(path) = &history {
+        let _ = rl.load_history(path);
+    }
+
+    let mut line = 1;
+    loop {
+        let mut buffer = String::new();
+        // Read one logical entry, continuing across lines while the input ends
+        // mid-construct (unterminated string, unbalanced brackets, dangling
+        // operator) rather than reporting a premature error.
+        loop {
+            let prompt = if buffer.is_empty() {
+                format!("[{}] ", line)
+            } else {
+                "   ... ".to_string()
+            };
+            match rl.readline(&prompt) {
+                Ok(input) => {
+                    buffer.push_str(&input);
+                    buffer.push('\n');
+                    if !needs_more_input(&buffer) {
+                        break;
                     }
-                    return Err(LoxError { exit_code: 65 });
                 }
-            }
-        }
-        Err(scanner::ScanError {
-            cause,
-            line,
-            position,
-        }) => {
-            match cause {
-                scanner::ScanErrorType::BadChar(c) => {
-                    error(line, format!("Unexpected character {} at {}", c, position))
+                Err(ReadlineError::Interrupted) => {
+                    // Ctrl-C abandons the current entry.
+                    buffer.clear();
+                    break;
                 }
-                scanner::ScanErrorType::UnterminatedString(s) => {
-                    error(line, format!("Unterminated string {} at {}", s, position))
+                Err(ReadlineError::Eof) => {
+                    if let Some(path) = &history {
+                        let _ = rl.save_history(path);
+                    }
+                    return;
+                }
+                Err(why) => {
+                    println!("error: {}", why);
+                    buffer.clear();
+                    break;
                 }
-                scanner::ScanErrorType::NumberParseError(s, e) => error(
-                    line,
-                    format!("Could not parse {} as a number at {} ({})", s, position, e),
-                ),
             }
-            return Err(LoxError { exit_code: 65 });
         }
+
+        if buffer.trim().is_empty() {
+            continue;
+        }
+        rl.add_history_entry(buffer.trim_end());
+        if let Err(err) = lox::run(&buffer, env.clone()) {
+            err.report(&buffer);
+        }
+        line += 1;
     }
 }
 
-pub fn error(line: u32, message: String) -> () {
-    report(line, "", message);
+/// Path to the persisted REPL history dotfile (`~/.lox_history`), or `None`
+/// when the home directory can't be determined.
+fn history_path() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(|home| Path::new(&home).join(".lox_history"))
 }
 
-pub fn report(line: u32, location: &str, message: String) -> () {
-    println!("[line {}] Error {}: {}", line, location, message);
+/// Decide whether `source` is an incomplete entry that the REPL should keep
+/// reading lines for, as opposed to a genuine error. True only when the input
+/// ends in the middle of a construct.
+fn needs_more_input(source: &str) -> bool {
+    let tokens = match scanner::scan_tokens(source) {
+        Ok(tokens) => tokens,
+        // The one scan failure that more input can fix is an unterminated
+        // string; any other scan error is real.
+        Err(scanner::ScanError { cause, .. }) => {
+            return matches!(cause, scanner::ScanErrorType::UnterminatedString(_))
+        }
+    };
+
+    let mut depth = 0i32;
+    for token in &tokens {
+        match token.token_type {
+            TokenType::LeftParen | TokenType::LeftBrace => depth += 1,
+            TokenType::RightParen | TokenType::RightBrace => depth -= 1,
+            _ => {}
+        }
+    }
+    if depth > 0 {
+        return true;
+    }
+
+    // A trailing binary/logical operator (ignoring the synthetic EOF token)
+    // means the expression isn't finished.
+    let last = tokens
+        .iter()
+        .rev()
+        .find(|t| t.token_type != TokenType::Eof);
+    matches!(
+        last.map(|t| t.token_type),
+        Some(
+            TokenType::Plus
+                | TokenType::Minus
+                | TokenType::Star
+                | TokenType::Slash
+                | TokenType::And
+                | TokenType::Or
+                | TokenType::EqualEqual
+                | TokenType::BangEqual
+                | TokenType::Greater
+                | TokenType::GreaterEqual
+                | TokenType::Less
+                | TokenType::LessEqual
+                | TokenType::Equal
+        )
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn an_open_brace_needs_more_input() {
+        assert!(needs_more_input("fun f() {\n"));
+    }
+
+    #[test]
+    fn an_open_paren_needs_more_input() {
+        assert!(needs_more_input("print (1 + 2\n"));
+    }
+
+    #[test]
+    fn a_trailing_operator_needs_more_input() {
+        assert!(needs_more_input("1 +\n"));
+    }
+
+    #[test]
+    fn a_complete_statement_does_not_need_more_input() {
+        assert!(!needs_more_input("print 1 + 2;\n"));
+    }
 }
@@ -0,0 +1,269 @@
+use std::collections::HashMap;
+
+use crate::parser::{Expr, Stmt};
+use crate::token::Token;
+
+/// Identity of a single variable-access (`Expr::Variable`/`Expr::Assign`)
+/// expression: the address of its node in the borrowed AST. Because the
+/// resolver and the interpreter walk the *same* `&[Stmt]`, a given access has
+/// the same address in both passes, so the depth table can be keyed on node
+/// identity without the two walks having to agree on an expression numbering.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct ExprId(pub usize);
+
+impl ExprId {
+    /// The id of an expression node, derived from its address in the AST.
+    pub fn of(expr: &Expr) -> ExprId {
+        ExprId(expr as *const Expr as usize)
+    }
+}
+
+/// Side table mapping each resolved variable access to the number of enclosing
+/// scopes that must be skipped to reach its declaration. Accesses absent from
+/// the table are globals.
+pub type Resolutions = HashMap<ExprId, usize>;
+
+#[derive(Debug)]
+pub struct ResolveError {
+    pub token: Token,
+    pub message: String,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum FunctionKind {
+    None,
+    Function,
+}
+
+pub struct Resolver {
+    scopes: Vec<HashMap<String, bool>>,
+    resolutions: Resolutions,
+    current_function: FunctionKind,
+    errors: Vec<ResolveError>,
+}
+
+/// Resolve the variable accesses in `stmts`, returning the depth side table or
+/// the collected scoping errors.
+pub fn resolve(stmts: &[Stmt]) -> Result<Resolutions, Vec<ResolveError>> {
+    let mut resolver = Resolver {
+        scopes: Vec::new(),
+        resolutions: HashMap::new(),
+        current_function: FunctionKind::None,
+        errors: Vec::new(),
+    };
+    resolver.resolve_stmts(stmts);
+    if resolver.errors.is_empty() {
+        Ok(resolver.resolutions)
+    } else {
+        Err(resolver.errors)
+    }
+}
+
+impl Resolver {
+    fn resolve_stmts(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            self.resolve_stmt(stmt);
+        }
+    }
+
+    fn resolve_stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Block(stmts) => {
+                self.begin_scope();
+                self.resolve_stmts(stmts);
+                self.end_scope();
+            }
+            Stmt::Var(name, initializer) => {
+                self.declare(name);
+                if let Some(expr) = initializer {
+                    self.resolve_expr(expr);
+                }
+                self.define(name);
+            }
+            Stmt::Function(name, params, body) => {
+                self.declare(name);
+                self.define(name);
+                self.resolve_function(params, body, FunctionKind::Function);
+            }
+            Stmt::Expression(expr) => self.resolve_expr(expr),
+            Stmt::Print(expr) => self.resolve_expr(expr),
+            Stmt::If(condition, then_branch, else_branch) => {
+                self.resolve_expr(condition);
+                self.resolve_stmt(then_branch);
+                if let Some(else_branch) = else_branch {
+                    self.resolve_stmt(else_branch);
+                }
+            }
+            Stmt::While(condition, body) => {
+                self.resolve_expr(condition);
+                self.resolve_stmt(body);
+            }
+            Stmt::Return(keyword, value) => {
+                if self.current_function == FunctionKind::None {
+                    self.error(keyword, "can't return from top-level code");
+                }
+                if let Some(expr) = value {
+                    self.resolve_expr(expr);
+                }
+            }
+        }
+    }
+
+    fn resolve_function(&mut self, params: &[Token], body: &[Stmt], kind: FunctionKind) {
+        let enclosing = self.current_function;
+        self.current_function = kind;
+        self.begin_scope();
+        for param in params {
+            self.declare(param);
+            self.define(param);
+        }
+        self.resolve_stmts(body);
+        self.end_scope();
+        self.current_function = enclosing;
+    }
+
+    fn resolve_expr(&mut self, expr: &Expr) {
+        match expr {
+            Expr::Variable(name) => {
+                if let Some(false) = self.scopes.last().and_then(|s| s.get(&name.lexeme)) {
+                    self.error(name, "can't read local variable in its own initializer");
+                }
+                self.resolve_local(ExprId::of(expr), name);
+            }
+            Expr::Assign(name, value) => {
+                self.resolve_expr(value);
+                self.resolve_local(ExprId::of(expr), name);
+            }
+            Expr::Binary(left, _, right) | Expr::Logical(left, _, right) => {
+                self.resolve_expr(left);
+                self.resolve_expr(right);
+            }
+            Expr::Call(callee, _, arguments) => {
+                self.resolve_expr(callee);
+                for argument in arguments {
+                    self.resolve_expr(argument);
+                }
+            }
+            Expr::Grouping(inner) => self.resolve_expr(inner),
+            Expr::Unary(_, right) => self.resolve_expr(right),
+            Expr::Literal(_, _) => {}
+        }
+    }
+
+    fn resolve_local(&mut self, id: ExprId, name: &Token) {
+        for (depth, scope) in self.scopes.iter().rev().enumerate() {
+            if scope.contains_key(&name.lexeme) {
+                self.resolutions.insert(id, depth);
+                return;
+            }
+        }
+        // Unresolved: assume global, leave out of the table.
+    }
+
+    fn declare(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            if scope.contains_key(&name.lexeme) {
+                self.errors.push(ResolveError {
+                    token: name.clone(),
+                    message: "already a variable with this name in this scope".to_string(),
+                });
+                return;
+            }
+            scope.insert(name.lexeme.clone(), false);
+        }
+    }
+
+    fn define(&mut self, name: &Token) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.insert(name.lexeme.clone(), true);
+        }
+    }
+
+    fn begin_scope(&mut self) {
+        self.scopes.push(HashMap::new());
+    }
+
+    fn end_scope(&mut self) {
+        self.scopes.pop();
+    }
+
+    fn error(&mut self, token: &Token, message: &str) {
+        self.errors.push(ResolveError {
+            token: token.clone(),
+            message: message.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagnostics::Span;
+    use crate::interpreter::Value;
+    use crate::token::TokenType;
+
+    fn ident(name: &str, line: u32) -> Token {
+        Token {
+            token_type: TokenType::Identifier,
+            lexeme: name.to_string(),
+            line,
+            column: 1,
+            span: Span::new(0, name.len()),
+        }
+    }
+
+    fn num(n: f64, line: u32) -> Expr {
+        Expr::Literal(Value::Number(n), line)
+    }
+
+    #[test]
+    fn self_reference_in_initializer_is_an_error() {
+        let stmts = vec![Stmt::Block(vec![Stmt::Var(
+            ident("a", 1),
+            Some(Expr::Variable(ident("a", 1))),
+        )])];
+        let errs = resolve(&stmts).expect_err("initializer reads its own declaration");
+        assert!(errs
+            .iter()
+            .any(|e| e.message.contains("its own initializer")));
+    }
+
+    #[test]
+    fn duplicate_local_redeclaration_is_an_error() {
+        let stmts = vec![Stmt::Block(vec![
+            Stmt::Var(ident("a", 1), Some(num(1.0, 1))),
+            Stmt::Var(ident("a", 2), Some(num(2.0, 2))),
+        ])];
+        let errs = resolve(&stmts).expect_err("second `a` redeclares the first in one scope");
+        assert!(errs.iter().any(|e| e.message.contains("already a variable")));
+    }
+
+    #[test]
+    fn return_outside_a_function_is_an_error() {
+        let stmts = vec![Stmt::Return(ident("return", 1), None)];
+        let errs = resolve(&stmts).expect_err("return at top level");
+        assert!(errs.iter().any(|e| e.message.contains("top-level code")));
+    }
+
+    #[test]
+    fn variable_access_resolves_to_the_correct_enclosing_depth() {
+        let stmts = vec![Stmt::Block(vec![
+            Stmt::Var(ident("a", 1), Some(num(1.0, 1))),
+            Stmt::Block(vec![Stmt::Expression(Expr::Variable(ident("a", 2)))]),
+        ])];
+        let resolutions = resolve(&stmts).expect("both scopes resolve cleanly");
+
+        let access = match &stmts[0] {
+            Stmt::Block(outer) => match &outer[1] {
+                Stmt::Block(inner) => match &inner[0] {
+                    Stmt::Expression(expr) => expr,
+                    _ => unreachable!(),
+                },
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        // One scope hop from the inner block up to where `a` is declared.
+        assert_eq!(resolutions.get(&ExprId::of(access)), Some(&1));
+    }
+}
@@ -0,0 +1,60 @@
+use crate::diagnostics::Span;
+
+/// The kind of lexeme a [`Token`] represents.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TokenType {
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    Comma,
+    Dot,
+    Minus,
+    Plus,
+    Semicolon,
+    Slash,
+    Star,
+
+    Bang,
+    BangEqual,
+    Equal,
+    EqualEqual,
+    Greater,
+    GreaterEqual,
+    Less,
+    LessEqual,
+
+    Identifier,
+    String,
+    Number,
+
+    And,
+    Else,
+    False,
+    Fun,
+    For,
+    If,
+    Nil,
+    Or,
+    Print,
+    Return,
+    True,
+    Var,
+    While,
+
+    Eof,
+}
+
+/// A single lexical token, carrying its own position: the 1-indexed line and
+/// column it starts on, and its exact byte span in the source. The scanner
+/// fills these in as it produces tokens, so later diagnostics can point at
+/// the precise occurrence instead of re-searching the reported line's text
+/// for the lexeme, which is ambiguous whenever it repeats on that line.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token {
+    pub token_type: TokenType,
+    pub lexeme: String,
+    pub line: u32,
+    pub column: usize,
+    pub span: Span,
+}
@@ -0,0 +1,236 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use crate::chunk::{Chunk, Op};
+use crate::environment::Environment;
+use crate::interpreter::{RuntimeError, Value};
+
+/// A stack-based virtual machine that executes a compiled [`Chunk`]. Globals
+/// live in the shared [`Environment`] so they survive across REPL entries just
+/// like the tree-walking interpreter's do.
+#[derive(Default)]
+pub struct Vm {
+    stack: Vec<Value>,
+    ip: usize,
+}
+
+impl Vm {
+    pub fn new() -> Vm {
+        Vm {
+            stack: Vec::new(),
+            ip: 0,
+        }
+    }
+
+    pub fn run(
+        &mut self,
+        chunk: &Chunk,
+        env: Rc<RefCell<Environment>>,
+    ) -> Result<(), Vec<RuntimeError>> {
+        self.ip = 0;
+        while self.ip < chunk.code.len() {
+            let op = &chunk.code[self.ip];
+            let line = chunk.lines[self.ip];
+            match op {
+                Op::Constant(idx) => self.push(chunk.constants[*idx].clone()),
+                Op::Nil => self.push(Value::Nil),
+                Op::True => self.push(Value::Boolean(true)),
+                Op::False => self.push(Value::Boolean(false)),
+                Op::Pop => {
+                    self.pop();
+                }
+                Op::DefineGlobal(idx) => {
+                    let value = self.pop();
+                    env.borrow_mut().define(chunk.names[*idx].clone(), value);
+                }
+                Op::GetGlobal(idx) => {
+                    let name = &chunk.names[*idx];
+                    match env.borrow().get(name) {
+                        Some(value) => self.push(value),
+                        None => return Err(vec![undefined(name, line)]),
+                    }
+                }
+                Op::SetGlobal(idx) => {
+                    let name = &chunk.names[*idx];
+                    let value = self.peek().clone();
+                    if env.borrow_mut().assign(name, value).is_err() {
+                        return Err(vec![undefined(name, line)]);
+                    }
+                }
+                Op::GetLocal(slot) => self.push(self.stack[*slot].clone()),
+                Op::SetLocal(slot) => {
+                    let slot = *slot;
+                    self.stack[slot] = self.peek().clone();
+                }
+                Op::Equal => {
+                    let b = self.pop();
+                    let a = self.pop();
+                    self.push(Value::Boolean(a == b));
+                }
+                Op::Greater => self.compare(line, |a, b| a > b)?,
+                Op::Less => self.compare(line, |a, b| a < b)?,
+                Op::Add => {
+                    let b = self.pop();
+                    let a = self.pop();
+                    match (a, b) {
+                        (Value::Number(a), Value::Number(b)) => self.push(Value::Number(a + b)),
+                        (Value::String(a), Value::String(b)) => {
+                            self.push(Value::String(format!("{}{}", a, b)))
+                        }
+                        _ => {
+                            return Err(vec![operand_error(
+                                line,
+                                "operands must be two numbers or two strings",
+                            )])
+                        }
+                    }
+                }
+                Op::Subtract => self.arithmetic(line, |a, b| a - b)?,
+                Op::Multiply => self.arithmetic(line, |a, b| a * b)?,
+                Op::Divide => self.arithmetic(line, |a, b| a / b)?,
+                Op::Not => {
+                    let value = self.pop();
+                    self.push(Value::Boolean(!truthy(&value)));
+                }
+                Op::Negate => match self.pop() {
+                    Value::Number(n) => self.push(Value::Number(-n)),
+                    _ => return Err(vec![operand_error(line, "operand must be a number")]),
+                },
+                Op::Print => {
+                    let value = self.pop();
+                    println!("{}", value);
+                }
+                Op::JumpIfFalse(offset) => {
+                    if !truthy(self.peek()) {
+                        self.ip += offset;
+                    }
+                }
+                Op::Jump(offset) => self.ip += offset,
+                Op::Loop(offset) => {
+                    self.ip -= offset;
+                    continue;
+                }
+                Op::Call(_argc) => unreachable!(
+                    "calls are rejected by Interpreter::unsupported before the \
+                     bytecode backend runs"
+                ),
+                Op::Return => return Ok(()),
+            }
+            self.ip += 1;
+        }
+        Ok(())
+    }
+
+    fn arithmetic<F: Fn(f64, f64) -> f64>(
+        &mut self,
+        line: u32,
+        op: F,
+    ) -> Result<(), Vec<RuntimeError>> {
+        let b = self.pop();
+        let a = self.pop();
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => {
+                self.push(Value::Number(op(a, b)));
+                Ok(())
+            }
+            _ => Err(vec![operand_error(line, "operands must be numbers")]),
+        }
+    }
+
+    fn compare<F: Fn(f64, f64) -> bool>(
+        &mut self,
+        line: u32,
+        op: F,
+    ) -> Result<(), Vec<RuntimeError>> {
+        let b = self.pop();
+        let a = self.pop();
+        match (a, b) {
+            (Value::Number(a), Value::Number(b)) => {
+                self.push(Value::Boolean(op(a, b)));
+                Ok(())
+            }
+            _ => Err(vec![operand_error(line, "operands must be numbers")]),
+        }
+    }
+
+    fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    fn pop(&mut self) -> Value {
+        self.stack
+            .pop()
+            .expect("stack underflow: compiler emitted unbalanced bytecode")
+    }
+
+    fn peek(&self) -> &Value {
+        self.stack
+            .last()
+            .expect("stack underflow: compiler emitted unbalanced bytecode")
+    }
+}
+
+fn truthy(value: &Value) -> bool {
+    !matches!(value, Value::Nil | Value::Boolean(false))
+}
+
+fn undefined(name: &str, line: u32) -> RuntimeError {
+    RuntimeError {
+        expr: name.to_string(),
+        line,
+        // The compiler erases names to indices into `chunk.names` and
+        // doesn't keep the originating token, so there's no span to hand
+        // back here; `report_runtime` falls back to a source line search.
+        column: None,
+        span: None,
+        message: format!("undefined variable '{}'", name),
+    }
+}
+
+fn operand_error(line: u32, message: &str) -> RuntimeError {
+    RuntimeError {
+        expr: String::new(),
+        line,
+        column: None,
+        span: None,
+        message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+
+    #[test]
+    fn top_level_while_loops_without_underflow() {
+        // Hand-assembled `while (i > 0) { i = i - 1; }` with the loop condition
+        // as the very first instruction (loop_start = 0) — the case whose
+        // back-edge underflowed `ip` before the offset fix.
+        let mut chunk = Chunk::new();
+        let i = chunk.add_name("i");
+        let zero = chunk.add_constant(Value::Number(0.0));
+        let one = chunk.add_constant(Value::Number(1.0));
+        chunk.write(Op::GetGlobal(i), 1); // 0  loop_start
+        chunk.write(Op::Constant(zero), 1); // 1
+        chunk.write(Op::Greater, 1); // 2
+        let exit = chunk.write(Op::JumpIfFalse(0), 1); // 3
+        chunk.write(Op::Pop, 1); // 4
+        chunk.write(Op::GetGlobal(i), 1); // 5
+        chunk.write(Op::Constant(one), 1); // 6
+        chunk.write(Op::Subtract, 1); // 7
+        chunk.write(Op::SetGlobal(i), 1); // 8
+        chunk.write(Op::Pop, 1); // 9
+        chunk.write(Op::Loop(10), 1); // 10 -> rewinds to 0
+        let after = chunk.write(Op::Pop, 1); // 11  exit lands here
+        chunk.write(Op::Return, 1); // 12
+        if let Op::JumpIfFalse(slot) = &mut chunk.code[exit] {
+            *slot = after - exit - 1;
+        }
+
+        let env = Rc::new(RefCell::new(Environment::new(None)));
+        env.borrow_mut().define("i".to_string(), Value::Number(2.0));
+        Vm::new().run(&chunk, env.clone()).unwrap();
+        assert_eq!(env.borrow().get("i"), Some(Value::Number(0.0)));
+    }
+}